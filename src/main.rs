@@ -1,4 +1,5 @@
 use macroquad::prelude::*;
+use std::collections::HashMap;
 
 // ==================== 定数定義 ==================== //
 
@@ -10,24 +11,53 @@ const STIFFNESS_AFTER: f32 = 300.0;      // 爆発後のばね定数（弾性の
 const DAMPING_AFTER: f32 = 2.0;          // 爆発後の減衰係数（粘性）
 const GRAVITY: Vec2 = vec2(0.0, 500.0);  // 重力ベクトル（下向き）
 const REBOUND: f32 = -0.3;               // 地面に当たったときの反発係数
+const STEP: f32 = 1.0 / 200.0;           // 物理演算の固定タイムステップ（秒）
+const GROUND_FRICTION: f32 = 4.0;        // 地面接地時の接線方向の摩擦係数
+const SPRAY_COUNT: usize = 120;          // 爆発時に飛び散る飛沫の数
+const SPRAY_MIN_SPEED: f32 = 80.0;       // 飛沫の初速（下限）
+const SPRAY_MAX_SPEED: f32 = 260.0;      // 飛沫の初速（上限）
+const SPRAY_RADIAL_ACCEL: f32 = 40.0;    // 爆心から外向きの加速度
+const SPRAY_TANGENTIAL_ACCEL: f32 = 90.0;// 爆心周りの接線方向の加速度（渦を作る）
+const SPRAY_MIN_LIFE: f32 = 0.4;         // 飛沫の寿命（下限・秒）
+const SPRAY_MAX_LIFE: f32 = 1.1;         // 飛沫の寿命（上限・秒）
+const WALL_MARGIN: f32 = 40.0;           // 壁回避ステアリングが働き始める縁からの距離
+const WALL_MAX_SPEED: f32 = 250.0;       // 壁から離れる方向への目標速度の上限
+const WALL_MAX_FORCE: f32 = 600.0;       // 壁ごとのステアリング力の上限
+const WATER_SURFACE_RATIO: f32 = 0.82;   // 水面の高さ（画面高さに対する割合）
+const BUOYANCY_COEFF: f32 = 900.0;       // 水没した深さ1pxあたりの浮力係数
+const WATER_DRAG: f32 = 3.0;             // 水中での速度に対する抵抗係数
 
 // ==================== 構造体定義 ==================== //
 
-/// 水粒子（位置・速度・外力）を表現する構造体
+/// 水粒子（位置・速度・外力・質量）を表現する構造体
 struct Particle {
     position: Vec2,
     velocity: Vec2,
     force: Vec2,
+    mass: f32,
 }
 
-/// 弾丸（発射位置・速度・半径・有効フラグ）を表現する構造体
+/// 弾丸（発射位置・速度・加速度・半径・有効フラグ）を表現する構造体
 struct Bullet {
     position: Vec2,
     velocity: Vec2,
+    acceleration: Vec2,
     radius: f32,
     active: bool,
 }
 
+/// 爆発の飛沫（破片）を表現する構造体
+struct Spray {
+    position: Vec2,
+    velocity: Vec2,
+    acceleration: Vec2,
+    size: f32,
+    initial_size: f32,
+    color: Color,
+    remaining_life: f32,
+    life: f32,
+}
+
 // ==================== 水粒子の処理 ==================== //
 
 impl Particle {
@@ -36,6 +66,7 @@ impl Particle {
             position: pos,
             velocity: Vec2::ZERO,
             force: Vec2::ZERO,
+            mass: 1.0,
         }
     }
 
@@ -49,14 +80,55 @@ impl Particle {
         self.force = Vec2::ZERO;
     }
 
-    /// 重力を適用する
+    /// 重力を適用する（質量に比例させ、落下加速度が質量に依らないようにする）
     fn apply_gravity(&mut self) {
-        self.apply_force(GRAVITY);
+        self.apply_force(GRAVITY * self.mass);
+    }
+
+    /// 画面の四辺に近づいたとき、壁から離れる向きのステアリング力を加える
+    ///
+    /// 各壁について「目標速度 - 現在速度」の差分を `steer` とし、大きさを
+    /// `WALL_MAX_FORCE` にクランプしてから加算する。複数の壁に同時に近い
+    /// （角付近にいる）場合はそれぞれの寄与が合算される。
+    fn apply_wall_avoidance(&mut self) {
+        let mut steer_sum = Vec2::ZERO;
+
+        let margin = WALL_MARGIN;
+        let walls = [
+            (self.position.x, vec2(1.0, 0.0)),                      // 左壁 → 右向きに押し返す
+            (screen_width() - self.position.x, vec2(-1.0, 0.0)),    // 右壁 → 左向きに押し返す
+            (self.position.y, vec2(0.0, 1.0)),                      // 上壁 → 下向きに押し返す
+            (screen_height() - self.position.y, vec2(0.0, -1.0)),   // 下壁 → 上向きに押し返す
+        ];
+
+        for (distance, away_dir) in walls {
+            if distance < margin {
+                let desired = away_dir * WALL_MAX_SPEED;
+                let steer = (desired - self.velocity).clamp_length_max(WALL_MAX_FORCE);
+                steer_sum += steer;
+            }
+        }
+
+        self.apply_force(steer_sum);
+    }
+
+    /// 水面より下に沈んでいる場合、浮力と水中抵抗を加える
+    ///
+    /// 浮力は水没した深さに比例し（アルキメデスの原理の簡易近似）、
+    /// 抵抗は速度に比例して働くため、重力との釣り合いで一定の深さに落ち着く。
+    fn apply_buoyancy(&mut self) {
+        let surface_y = water_surface_y();
+        let depth = self.position.y - surface_y;
+        if depth > 0.0 {
+            let buoyancy = vec2(0.0, -BUOYANCY_COEFF * depth);
+            let drag = -self.velocity * WATER_DRAG;
+            self.apply_force(buoyancy + drag);
+        }
     }
 
-    /// 速度・位置を更新（半陰的オイラー法）
+    /// 速度・位置を更新（半陰的オイラー法、ニュートンの運動方程式 F = ma に基づく）
     fn update(&mut self, dt: f32) {
-        self.velocity += self.force * dt;
+        self.velocity += (self.force / self.mass) * dt;
         self.position += self.velocity * dt;
         self.reset_force();
 
@@ -64,6 +136,9 @@ impl Particle {
         if self.position.y + PARTICLE_RADIUS > screen_height() {
             self.position.y = screen_height() - PARTICLE_RADIUS;
             self.velocity.y *= REBOUND;
+
+            // 接線方向（水平方向）の摩擦で徐々に静止させる
+            self.velocity.x *= (1.0 - GROUND_FRICTION * dt).max(0.0);
         }
     }
 
@@ -76,19 +151,20 @@ impl Particle {
 // ==================== 弾の処理 ==================== //
 
 impl Bullet {
-    fn new(start: Vec2, target: Vec2) -> Self {
-        let dir = (target - start).normalize();
-        let speed = 800.0;
+    /// 指定した方向・速さ・加速度で弾を生成する（弾幕パターン用）
+    fn with_velocity(start: Vec2, velocity: Vec2, acceleration: Vec2) -> Self {
         Self {
             position: start,
-            velocity: dir * speed,
+            velocity,
+            acceleration,
             radius: 5.0,
             active: true,
         }
     }
 
-    /// 弾の位置を更新
+    /// 弾の速度・位置を更新（加速度を持つ弾は軌道が曲がる/加減速する）
     fn update(&mut self, dt: f32) {
+        self.velocity += self.acceleration * dt;
         self.position += self.velocity * dt;
 
         // 画面外に出たら非アクティブ化
@@ -110,33 +186,162 @@ impl Bullet {
     }
 }
 
+// ==================== 弾幕パターン生成 ==================== //
+
+const BULLET_SPEED: f32 = 800.0;      // 弾幕の基準速度
+const FAN_SPREAD_COUNT: usize = 7;    // 扇状弾幕の弾数
+const FAN_SPREAD_ANGLE: f32 = 0.6;    // 扇状弾幕の広がり角（ラジアン）
+const RING_BULLET_COUNT: usize = 16;  // 円形弾幕の弾数
+
+/// `center` を中心に `count` 発を円状に均等配置して発射する
+fn spawn_ring(center: Vec2, count: usize, speed: f32) -> Vec<Bullet> {
+    (0..count)
+        .map(|i| {
+            let theta = i as f32 / count as f32 * 2.0 * std::f32::consts::PI;
+            let dir = vec2(theta.cos(), theta.sin());
+            Bullet::with_velocity(center, dir * speed, Vec2::ZERO)
+        })
+        .collect()
+}
+
+/// `origin` から `target` 方向を中心に `spread` ラジアンの扇状に `count` 発を発射する
+fn spawn_fan(origin: Vec2, target: Vec2, spread: f32, count: usize) -> Vec<Bullet> {
+    let aim = target - origin;
+    let base_angle = aim.y.atan2(aim.x);
+    let start_angle = base_angle - spread / 2.0;
+
+    (0..count)
+        .map(|i| {
+            let t = if count > 1 { i as f32 / (count - 1) as f32 } else { 0.5 };
+            let theta = start_angle + spread * t;
+            let dir = vec2(theta.cos(), theta.sin());
+            Bullet::with_velocity(origin, dir * BULLET_SPEED, Vec2::ZERO)
+        })
+        .collect()
+}
+
+// ==================== 飛沫（爆発エフェクト）の処理 ==================== //
+
+impl Spray {
+    /// 爆心 `center` から放射状に飛び散る飛沫を1つ生成する
+    fn new(center: Vec2) -> Self {
+        let angle = macroquad::rand::gen_range(0.0, std::f32::consts::TAU);
+        let speed = macroquad::rand::gen_range(SPRAY_MIN_SPEED, SPRAY_MAX_SPEED);
+        let dir = vec2(angle.cos(), angle.sin());
+        let life = macroquad::rand::gen_range(SPRAY_MIN_LIFE, SPRAY_MAX_LIFE);
+        let size = macroquad::rand::gen_range(1.5, 3.5);
+
+        Self {
+            position: center,
+            velocity: dir * speed,
+            acceleration: Vec2::ZERO,
+            size,
+            initial_size: size,
+            color: Color::new(0.4, 0.7, 1.0, 0.9),
+            remaining_life: life,
+            life,
+        }
+    }
+
+    /// 爆心からの半径方向・接線方向の加速度を与えたうえで半陰的オイラー法で積分する
+    fn update(&mut self, center: Vec2, dt: f32) {
+        let offset = self.position - center;
+        let dist = offset.length().max(0.01);
+        let radial_dir = offset / dist;
+        let tangential_dir = vec2(-radial_dir.y, radial_dir.x);
+
+        self.acceleration = radial_dir * SPRAY_RADIAL_ACCEL + tangential_dir * SPRAY_TANGENTIAL_ACCEL;
+
+        self.velocity += self.acceleration * dt;
+        self.position += self.velocity * dt;
+
+        self.remaining_life -= dt;
+        let t = (self.remaining_life / self.life).clamp(0.0, 1.0);
+        self.size = self.initial_size * t;
+        self.color.a = t;
+    }
+
+    /// 寿命が残っているかどうか
+    fn is_alive(&self) -> bool {
+        self.remaining_life > 0.0
+    }
+
+    /// 飛沫を描画する
+    fn draw(&self) {
+        draw_circle(self.position.x, self.position.y, self.size, self.color);
+    }
+}
+
+/// 衝突地点を中心に飛沫のバーストを生成する
+fn spawn_spray_burst(center: Vec2) -> Vec<Spray> {
+    (0..SPRAY_COUNT).map(|_| Spray::new(center)).collect()
+}
+
 // ==================== 粘弾性ばね力の適用 ==================== //
 
+/// 相互作用半径（この距離を超える粒子対は一切バネ力を及ぼさない）
+const INTERACTION_RADIUS: f32 = REST_LENGTH * 2.0;
+
+/// 粒子群を一様グリッドに分割し、セル座標ごとの粒子インデックス一覧を返す
+///
+/// セルサイズは相互作用半径と一致させてあるので、ある粒子と相互作用しうる
+/// 相手は必ず自分のセルか隣接8セルのどこかに入っている。
+fn build_spatial_grid(particles: &[Particle]) -> HashMap<(i32, i32), Vec<usize>> {
+    let mut grid: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+    for (i, p) in particles.iter().enumerate() {
+        let cell = (
+            (p.position.x / INTERACTION_RADIUS).floor() as i32,
+            (p.position.y / INTERACTION_RADIUS).floor() as i32,
+        );
+        grid.entry(cell).or_default().push(i);
+    }
+    grid
+}
+
 /// 近接する粒子間にフックの法則と粘性減衰を適用する
+///
+/// 全粒子対を総当たりすると O(n^2) になるため、セルサイズを相互作用半径に
+/// 合わせた一様グリッドで近傍探索を絞り込む（ブロードフェーズ）。
 fn apply_spring_forces(particles: &mut Vec<Particle>) {
     let stiffness = STIFFNESS_AFTER;
     let damping = DAMPING_AFTER;
 
-    let n = particles.len();
-    for i in 0..n {
-        for j in (i + 1)..n {
-            let delta = particles[j].position - particles[i].position;
-            let dist = delta.length();
-            if dist < REST_LENGTH * 2.0 && dist > 0.01 {
-                let dir = delta.normalize();
-                let x = dist - REST_LENGTH;
-
-                // フックの法則によるばね力
-                let f_spring = dir * (stiffness * x);
-
-                // 相対速度によるダンピング
-                let v_rel = particles[j].velocity - particles[i].velocity;
-                let f_damp = dir * (v_rel.dot(dir) * damping);
-
-                let force = f_spring + f_damp;
-
-                particles[i].apply_force(force);
-                particles[j].apply_force(-force);
+    let grid = build_spatial_grid(particles);
+
+    for (&(cx, cy), cell_indices) in grid.iter() {
+        for oy in -1..=1 {
+            for ox in -1..=1 {
+                let Some(neighbor_indices) = grid.get(&(cx + ox, cy + oy)) else {
+                    continue;
+                };
+
+                for &i in cell_indices {
+                    for &j in neighbor_indices {
+                        // 各ペアは j > i のときのみ処理し、重複・自己対を避ける
+                        if j <= i {
+                            continue;
+                        }
+
+                        let delta = particles[j].position - particles[i].position;
+                        let dist = delta.length();
+                        if dist < INTERACTION_RADIUS && dist > 0.01 {
+                            let dir = delta.normalize();
+                            let x = dist - REST_LENGTH;
+
+                            // フックの法則によるばね力
+                            let f_spring = dir * (stiffness * x);
+
+                            // 相対速度によるダンピング
+                            let v_rel = particles[j].velocity - particles[i].velocity;
+                            let f_damp = dir * (v_rel.dot(dir) * damping);
+
+                            let force = f_spring + f_damp;
+
+                            particles[i].apply_force(force);
+                            particles[j].apply_force(-force);
+                        }
+                    }
+                }
             }
         }
     }
@@ -171,17 +376,59 @@ fn generate_spherical_particles(center: Vec2, count: usize, radius: f32) -> Vec<
     particles
 }
 
+// ==================== 水域の処理 ==================== //
+
+/// 水面のy座標（画面上端からの、画面高さに対する割合で決まる）
+fn water_surface_y() -> f32 {
+    screen_height() * WATER_SURFACE_RATIO
+}
+
+/// 水域を半透明の矩形として描画する
+fn draw_water_region() {
+    let surface_y = water_surface_y();
+    draw_rectangle(
+        0.0,
+        surface_y,
+        screen_width(),
+        screen_height() - surface_y,
+        Color::new(0.1, 0.3, 0.6, 0.35),
+    );
+}
+
+// ==================== 物理演算ステップ ==================== //
+
+/// 重力・ばね力・積分を固定タイムステップ分だけ進める
+///
+/// 実時間の `dt` をそのまま積分に使うとフレームレートが落ちたときに
+/// `STIFFNESS_AFTER` のような硬いばね定数が不安定化してしまうため、
+/// 呼び出し側で `STEP` 刻みのアキュムレータを回して常に同じ刻み幅で呼ぶ。
+fn run_physics(particles: &mut Vec<Particle>, step: f32) {
+    for p in particles.iter_mut() {
+        p.apply_gravity();
+        p.apply_wall_avoidance();
+        p.apply_buoyancy();
+    }
+    apply_spring_forces(particles);
+    for p in particles.iter_mut() {
+        p.update(step);
+    }
+}
+
 // ==================== メインループ ==================== //
 
 #[macroquad::main("Perfect Spherical Water Balloon")]
 async fn main() {
     let mut particles: Vec<Particle> = Vec::new();   // 水風船の粒子群
     let mut bullets: Vec<Bullet> = Vec::new();       // 弾の配列
+    let mut sprays: Vec<Spray> = Vec::new();         // 爆発の飛沫（破片）
     let mut exploded = false;                        // 爆発済みフラグ
     let mut initialized = false;                     // 初期化済みフラグ
+    let mut accumulator = 0.0;                       // 固定ステップ積分用の時間バッファ
+    let mut impact_point = Vec2::ZERO;               // 爆発の衝突地点（飛沫の爆心）
 
     loop {
         clear_background(BLACK);
+        draw_water_region();
         let dt = get_frame_time();
 
         // 初期化（画面サイズ取得後に実行）
@@ -191,38 +438,54 @@ async fn main() {
             initialized = true;
         }
 
-        // マウスクリックで弾を発射
+        // 左クリックで照準方向への扇状弾幕、右クリックで全方位の円形弾幕を発射
+        let fire_origin = vec2(screen_width() / 2.0, screen_height());
         if is_mouse_button_pressed(MouseButton::Left) {
             let (mx, my) = mouse_position();
-            bullets.push(Bullet::new(vec2(screen_width() / 2.0, screen_height()), vec2(mx, my)));
+            bullets.extend(spawn_fan(fire_origin, vec2(mx, my), FAN_SPREAD_ANGLE, FAN_SPREAD_COUNT));
+        }
+        if is_mouse_button_pressed(MouseButton::Right) {
+            bullets.extend(spawn_ring(fire_origin, RING_BULLET_COUNT, BULLET_SPEED));
         }
 
         // 弾と水風船の衝突判定（初回のみ）
         for bullet in bullets.iter_mut() {
             bullet.update(dt);
             if !exploded {
-                if particles.iter().any(|p| bullet.collides_with(p)) {
+                if let Some(hit) = particles.iter().find(|p| bullet.collides_with(p)) {
                     exploded = true;
+                    impact_point = hit.position;
+                    sprays = spawn_spray_burst(impact_point);
                 }
             }
         }
 
-        // 爆発後は物理シミュレーションを適用
+        // 爆発後は固定タイムステップで物理シミュレーションを適用
+        // （描画はディスプレイのフレームレートのまま進める）
         if exploded {
-            for p in particles.iter_mut() {
-                p.apply_gravity();
-            }
-            apply_spring_forces(&mut particles);
-            for p in particles.iter_mut() {
-                p.update(dt);
+            accumulator += dt;
+            while accumulator >= STEP {
+                run_physics(&mut particles, STEP);
+                accumulator -= STEP;
             }
         }
 
+        // 飛沫の更新・削除処理
+        for s in sprays.iter_mut() {
+            s.update(impact_point, dt);
+        }
+        sprays.retain(|s| s.is_alive());
+
         // 粒子描画
         for p in particles.iter() {
             p.draw();
         }
 
+        // 飛沫の描画
+        for s in sprays.iter() {
+            s.draw();
+        }
+
         // 弾の描画と削除処理
         bullets.retain(|b| b.active);
         for b in bullets.iter() {